@@ -28,11 +28,13 @@
 //! }
 //! ```
 
-use std::fs::File;
+use std::fs::{self, File};
 use std::path::Path;
 use std::io;
 use std::io::{BufReader, BufRead, Read, Write};
 
+use encoding_rs::Encoding;
+
 pub use io::Result;
 
 /// Read a file into `Vec<u8>` from the given path.
@@ -56,15 +58,82 @@ pub fn put<P: AsRef<Path>, Bytes: AsRef<[u8]>>(path: P, data: Bytes) -> io::Resu
     Ok(())
 }
 
+/// Creates a file at the given path with contents of `Vec<u8>` or `&[u8]`, etc., creating
+/// any missing parent directories first.
+/// Overwrites, non-atomically, if the file exists.
+/// The path can be a string or a `Path`.
+pub fn put_mkdir<P: AsRef<Path>, Bytes: AsRef<[u8]>>(path: P, data: Bytes) -> io::Result<()> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    put(path, data)
+}
+
+/// Creates a file at the given path with contents of `Vec<u8>` or `&[u8]`, etc., atomically.
+/// Writes to a temporary sibling file, syncs it to disk, then renames it over the
+/// destination, so a concurrent reader or a crash mid-write always sees either the old
+/// contents or the complete new ones, never a partial file.
+/// The path can be a string or a `Path`.
+pub fn put_atomic<P: AsRef<Path>, Bytes: AsRef<[u8]>>(path: P, data: Bytes) -> io::Result<()> {
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    static NEXT_TMP_ID: AtomicU64 = AtomicU64::new(0);
+
+    let path = path.as_ref();
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+    let file_name = path.file_name().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, "path has no file name")
+    })?;
+    // Per-call id, not just the pid, so two overlapping calls in the same process (e.g. from
+    // different threads) never share a temp path.
+    let tmp_id = NEXT_TMP_ID.fetch_add(1, Ordering::Relaxed);
+    let tmp_path = dir.join(format!(
+        ".{}.tmp{}-{}",
+        file_name.to_string_lossy(),
+        std::process::id(),
+        tmp_id
+    ));
+
+    let write_result = (|| -> io::Result<()> {
+        let mut tmp_file = File::create(&tmp_path)?;
+        tmp_file.write_all(data.as_ref())?;
+        tmp_file.sync_all()
+    })();
+    if let Err(e) = write_result {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+
+    if let Err(e) = fs::rename(&tmp_path, path) {
+        let _ = fs::remove_file(&tmp_path);
+        return Err(e);
+    }
+    Ok(())
+}
+
 /// Read an UTF-8 encoded file into `String` from the given path.
 /// The path can be a string or a `Path`.
 pub fn get_text<P: AsRef<Path>>(path: P) -> io::Result<String> {
     let bytes = get(path)?;
-    String::from_utf8(bytes).map_err(|_| {
-        io::Error::new(io::ErrorKind::InvalidData, "file did not contain valid UTF-8")
+    String::from_utf8(bytes).map_err(|e| {
+        let offset = e.utf8_error().valid_up_to();
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("invalid UTF-8 at byte {}", offset),
+        )
     })
 }
 
+/// Read a file into `String` from the given path, replacing any invalid UTF-8 with
+/// U+FFFD instead of failing. Useful when you just want the text out of a binary-ish
+/// or partially-corrupt file and don't care about a few mangled characters.
+/// The path can be a string or a `Path`.
+pub fn get_text_lossy<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    let bytes = get(path)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
 /// Creates a file at the given path with given text contents, encoded as UTF-8.
 /// Overwrites, non-atomically, if the file exists.
 /// The path can be a string or a `Path`.
@@ -72,12 +141,113 @@ pub fn put_text<P: AsRef<Path>, S: AsRef<str>>(path: P, data: S) -> io::Result<(
     put(path, data.as_ref().as_bytes())
 }
 
+/// Read a file into `String` from the given path, decoding it with the given
+/// character encoding instead of assuming UTF-8.
+///
+/// If the file starts with a UTF-8 or UTF-16 byte-order mark, the BOM wins and is used
+/// to pick the encoding in place of `enc`, matching how most editors auto-detect encoding;
+/// the encoding actually applied is returned alongside the text. Also returns whether the
+/// decoder had to substitute replacement characters for invalid sequences, so callers can
+/// tell a lossy decode from a clean one.
+/// The path can be a string or a `Path`.
+pub fn get_text_with_encoding<P: AsRef<Path>>(
+    path: P,
+    enc: &'static Encoding,
+) -> io::Result<(String, &'static Encoding, bool)> {
+    let bytes = get(path)?;
+    let (text, used_enc, had_errors) = enc.decode(&bytes);
+    Ok((text.into_owned(), used_enc, had_errors))
+}
+
+/// Like [`get_text_with_encoding`], but treats a lossy decode (one that had to substitute
+/// replacement characters) as an error instead of silently returning it.
+/// The path can be a string or a `Path`.
+pub fn get_text_with_encoding_strict<P: AsRef<Path>>(
+    path: P,
+    enc: &'static Encoding,
+) -> io::Result<String> {
+    let (text, used_enc, had_errors) = get_text_with_encoding(path, enc)?;
+    if had_errors {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("file did not contain valid {}", used_enc.name()),
+        ));
+    }
+    Ok(text)
+}
+
+/// Creates a file at the given path with given text contents, encoded with the given
+/// character encoding. Pass `bom = true` to emit the encoding's byte-order mark first.
+/// Overwrites, non-atomically, if the file exists.
+/// The path can be a string or a `Path`.
+pub fn put_text_with_encoding<P: AsRef<Path>, S: AsRef<str>>(
+    path: P,
+    data: S,
+    enc: &'static Encoding,
+    bom: bool,
+) -> io::Result<()> {
+    let text = data.as_ref();
+    let mut out = Vec::new();
+    if bom {
+        out.extend_from_slice(bom_bytes(enc));
+    }
+    // `Encoding::encode` can't target UTF-16: its `output_encoding` is always UTF-8, since
+    // that's what a web form submits regardless of the page's declared charset. Encode
+    // those two cases by hand so round-tripping UTF-16LE/BE actually works.
+    if enc == encoding_rs::UTF_16LE {
+        out.extend(text.encode_utf16().flat_map(|u| u.to_le_bytes()));
+    } else if enc == encoding_rs::UTF_16BE {
+        out.extend(text.encode_utf16().flat_map(|u| u.to_be_bytes()));
+    } else {
+        let (bytes, _, _) = enc.encode(text);
+        out.extend_from_slice(&bytes);
+    }
+    put(path, out)
+}
+
+/// The byte-order mark for `enc`, or an empty slice if `enc` has no BOM.
+fn bom_bytes(enc: &'static Encoding) -> &'static [u8] {
+    if enc == encoding_rs::UTF_8 {
+        &[0xEF, 0xBB, 0xBF]
+    } else if enc == encoding_rs::UTF_16LE {
+        &[0xFF, 0xFE]
+    } else if enc == encoding_rs::UTF_16BE {
+        &[0xFE, 0xFF]
+    } else {
+        &[]
+    }
+}
+
 /// Reads text lines from the file
 /// Similar to Python' file('name').readlines()
 pub fn readlines<P: AsRef<Path>>(path: P) -> io::Result<Vec<String>> {
+    lines(path)?.collect()
+}
+
+/// Streams text lines from the file one at a time, instead of buffering the whole file
+/// like [`readlines`] does. Each item is an `io::Result<String>`, so a decode or I/O
+/// error on one line doesn't panic, it just surfaces as `Err`.
+/// The path can be a string or a `Path`.
+pub fn lines<P: AsRef<Path>>(path: P) -> io::Result<impl Iterator<Item = io::Result<String>>> {
     let f = File::open(path)?;
-    let buf = BufReader::new(f);
-    Ok(buf.lines().map(|l| l.unwrap()).collect::<Vec<_>>())
+    Ok(BufReader::new(f).lines())
+}
+
+/// Writes each item followed by `\n` to the file at the given path.
+/// Similar to Python's `file('name').writelines()`.
+/// Overwrites, non-atomically, if the file exists.
+/// The path can be a string or a `Path`.
+pub fn put_lines<P: AsRef<Path>, I: IntoIterator<Item = S>, S: AsRef<str>>(
+    path: P,
+    lines: I,
+) -> io::Result<()> {
+    let file = File::create(path)?;
+    let mut writer = io::BufWriter::new(file);
+    for line in lines {
+        writer.write_all(line.as_ref().as_bytes())?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
 }
 
 #[test]
@@ -115,6 +285,7 @@ fn it_works_with_text() {
     } else {
         panic!("Should error on invalid UTF-8")
     }
+    assert_eq!(get_text_lossy(&tmp_name).unwrap(), "\u{FFFD}");
 
     let text = "Hello, World!";
     put_text(&tmp_name, text).unwrap();
@@ -122,3 +293,179 @@ fn it_works_with_text() {
 
     std::fs::remove_file(tmp_name).ok();
 }
+
+#[test]
+fn readlines_and_lines_happy_path() {
+    let mut tmp_name = std::env::temp_dir();
+    tmp_name.push("readlines_and_lines_happy_path");
+
+    put_text(&tmp_name, "one\ntwo\nthree").unwrap();
+
+    assert_eq!(readlines(&tmp_name).unwrap(), vec!["one", "two", "three"]);
+
+    let via_iterator: io::Result<Vec<String>> = lines(&tmp_name).unwrap().collect();
+    assert_eq!(via_iterator.unwrap(), vec!["one", "two", "three"]);
+
+    std::fs::remove_file(&tmp_name).ok();
+}
+
+#[test]
+fn readlines_and_lines_surface_invalid_utf8_as_err_instead_of_panicking() {
+    let mut tmp_name = std::env::temp_dir();
+    tmp_name.push("readlines_and_lines_surface_invalid_utf8_as_err_instead_of_panicking");
+
+    put(&tmp_name, [b'o', b'k', b'\n', 0x80, b'\n']).unwrap();
+
+    if let Err(e) = readlines(&tmp_name) {
+        assert_eq!(e.kind(), io::ErrorKind::InvalidData);
+    } else {
+        panic!("Should error on invalid UTF-8 instead of panicking");
+    }
+
+    let mut it = lines(&tmp_name).unwrap();
+    assert_eq!(it.next().unwrap().unwrap(), "ok");
+    assert_eq!(it.next().unwrap().unwrap_err().kind(), io::ErrorKind::InvalidData);
+
+    std::fs::remove_file(&tmp_name).ok();
+}
+
+#[test]
+fn put_lines_and_readlines_roundtrip() {
+    let mut tmp_name = std::env::temp_dir();
+    tmp_name.push("put_lines_and_readlines_roundtrip");
+
+    let original = vec!["one", "two", "three"];
+    put_lines(&tmp_name, &original).unwrap();
+    assert_eq!(readlines(&tmp_name).unwrap(), original);
+
+    std::fs::remove_file(&tmp_name).ok();
+}
+
+#[test]
+fn put_atomic_writes_and_overwrites() {
+    let mut tmp_name = std::env::temp_dir();
+    tmp_name.push("put_atomic_writes_and_overwrites");
+
+    put_atomic(&tmp_name, b"first").unwrap();
+    assert_eq!(get(&tmp_name).unwrap(), b"first");
+
+    put_atomic(&tmp_name, b"second").unwrap();
+    assert_eq!(get(&tmp_name).unwrap(), b"second");
+
+    std::fs::remove_file(&tmp_name).ok();
+}
+
+#[test]
+fn put_atomic_leaves_no_temp_file_behind() {
+    let mut dir = std::env::temp_dir();
+    dir.push("put_atomic_leaves_no_temp_file_behind");
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let target = dir.join("target.txt");
+    put_atomic(&target, b"data").unwrap();
+
+    let entries: Vec<_> = std::fs::read_dir(&dir)
+        .unwrap()
+        .map(|e| e.unwrap().file_name())
+        .collect();
+    assert_eq!(entries, vec![std::ffi::OsString::from("target.txt")]);
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn put_mkdir_creates_missing_parent_dirs() {
+    let mut dir = std::env::temp_dir();
+    dir.push("put_mkdir_creates_missing_parent_dirs");
+    std::fs::remove_dir_all(&dir).ok();
+
+    let target = dir.join("a/b/c/file.txt");
+    assert!(put(&target, b"nested").is_err());
+    put_mkdir(&target, b"nested").unwrap();
+    assert_eq!(get(&target).unwrap(), b"nested");
+
+    std::fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn encoding_bom_overrides_requested_encoding() {
+    let mut tmp_name = std::env::temp_dir();
+    tmp_name.push("encoding_bom_overrides_requested_encoding");
+
+    // UTF-16LE BOM followed by "hi" as UTF-16LE, even though WINDOWS_1252 is requested.
+    put(&tmp_name, [0xFF, 0xFE, b'h', 0x00, b'i', 0x00]).unwrap();
+    let (text, used_enc, had_errors) =
+        get_text_with_encoding(&tmp_name, encoding_rs::WINDOWS_1252).unwrap();
+    assert_eq!(text, "hi");
+    assert_eq!(used_enc, encoding_rs::UTF_16LE);
+    assert!(!had_errors);
+
+    std::fs::remove_file(&tmp_name).ok();
+}
+
+#[test]
+fn encoding_windows_1252_roundtrip_without_bom() {
+    let mut tmp_name = std::env::temp_dir();
+    tmp_name.push("encoding_windows_1252_roundtrip_without_bom");
+
+    let text = "café";
+    put_text_with_encoding(&tmp_name, text, encoding_rs::WINDOWS_1252, false).unwrap();
+    let (decoded, used_enc, had_errors) =
+        get_text_with_encoding(&tmp_name, encoding_rs::WINDOWS_1252).unwrap();
+    assert_eq!(decoded, text);
+    assert_eq!(used_enc, encoding_rs::WINDOWS_1252);
+    assert!(!had_errors);
+
+    std::fs::remove_file(&tmp_name).ok();
+}
+
+#[test]
+fn encoding_utf16le_roundtrip_with_bom() {
+    let mut tmp_name = std::env::temp_dir();
+    tmp_name.push("encoding_utf16le_roundtrip_with_bom");
+
+    let text = "hello";
+    put_text_with_encoding(&tmp_name, text, encoding_rs::UTF_16LE, true).unwrap();
+    let bytes = get(&tmp_name).unwrap();
+    assert_eq!(&bytes[..2], &[0xFF, 0xFE]);
+
+    // WINDOWS_1252 is requested but the BOM should win and select UTF-16LE.
+    let (decoded, used_enc, had_errors) =
+        get_text_with_encoding(&tmp_name, encoding_rs::WINDOWS_1252).unwrap();
+    assert_eq!(decoded, text);
+    assert_eq!(used_enc, encoding_rs::UTF_16LE);
+    assert!(!had_errors);
+
+    std::fs::remove_file(&tmp_name).ok();
+}
+
+#[test]
+fn encoding_had_errors_and_strict_error() {
+    let mut tmp_name = std::env::temp_dir();
+    tmp_name.push("encoding_had_errors_and_strict_error");
+
+    put(&tmp_name, [0x80]).unwrap();
+    let (text, used_enc, had_errors) = get_text_with_encoding(&tmp_name, encoding_rs::UTF_8).unwrap();
+    assert!(had_errors);
+    assert_eq!(text, "\u{FFFD}");
+    assert_eq!(used_enc, encoding_rs::UTF_8);
+
+    assert!(get_text_with_encoding_strict(&tmp_name, encoding_rs::UTF_8).is_err());
+
+    std::fs::remove_file(&tmp_name).ok();
+}
+
+#[test]
+fn encoding_strict_error_names_the_bom_detected_encoding() {
+    let mut tmp_name = std::env::temp_dir();
+    tmp_name.push("encoding_strict_error_names_the_bom_detected_encoding");
+
+    // A UTF-16LE BOM followed by a lone byte: malformed UTF-16LE, even though
+    // WINDOWS_1252 is requested. The error message must name UTF-16LE, not
+    // windows-1252, since that's the encoding the BOM actually selected.
+    put(&tmp_name, [0xFF, 0xFE, 0x41]).unwrap();
+    let err = get_text_with_encoding_strict(&tmp_name, encoding_rs::WINDOWS_1252).unwrap_err();
+    assert!(err.to_string().contains("UTF-16LE"), "{}", err);
+
+    std::fs::remove_file(&tmp_name).ok();
+}